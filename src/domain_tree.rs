@@ -0,0 +1,113 @@
+//! A trie over reversed domain labels (TLD first), so `api.app.test`,
+//! `auth.app.test` and `cdn.app.test` all sit in the `app.test` subtree.
+//! This backs `--recursive` toggling (walk the subtree under a parent
+//! domain) and the O(labels) duplicate check in `add_domain`, replacing a
+//! linear `contains` scan over every hosts-file line.
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+pub struct DomainTree {
+    root: Node,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    /// Index into the caller's entry list, if one sits exactly at this node.
+    index: Option<usize>,
+}
+
+impl DomainTree {
+    /// Builds a tree from `(domain, index)` pairs, where `index` is
+    /// whatever the caller uses to look the entry back up (e.g. a position
+    /// in a `Vec<HostsPart>`). A leading `*.` is stripped before inserting,
+    /// so a wildcard entry for `*.app.test` sits at the same node as
+    /// `app.test` itself.
+    pub fn from_entries<I: IntoIterator<Item = (String, usize)>>(entries: I) -> DomainTree {
+        let mut tree = DomainTree::default();
+        for (domain, index) in entries {
+            tree.insert(&domain, index);
+        }
+        tree
+    }
+
+    fn insert(&mut self, domain: &str, index: usize) {
+        let domain = domain.strip_prefix("*.").unwrap_or(domain);
+        let mut node = &mut self.root;
+        for label in domain.split('.').rev() {
+            node = node.children.entry(label.to_string()).or_default();
+        }
+        node.index = Some(index);
+    }
+
+    /// True if `domain` has an entry in the tree.
+    pub fn contains(&self, domain: &str) -> bool {
+        self.get(domain).is_some()
+    }
+
+    /// The index stored exactly at `domain`, if any.
+    pub fn get(&self, domain: &str) -> Option<usize> {
+        self.lookup(domain)?.index
+    }
+
+    /// Every entry index in the subtree rooted at `domain`: `domain` itself
+    /// plus every descendant subdomain, in label order.
+    pub fn subtree(&self, domain: &str) -> Vec<usize> {
+        let mut out = Vec::new();
+        if let Some(node) = self.lookup(domain) {
+            collect(node, &mut out);
+        }
+        out
+    }
+
+    fn lookup(&self, domain: &str) -> Option<&Node> {
+        let domain = domain.strip_prefix("*.").unwrap_or(domain);
+        let mut node = &self.root;
+        for label in domain.split('.').rev() {
+            node = node.children.get(label)?;
+        }
+        Some(node)
+    }
+}
+
+fn collect(node: &Node, out: &mut Vec<usize>) {
+    if let Some(index) = node.index {
+        out.push(index);
+    }
+    for child in node.children.values() {
+        collect(child, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtree_finds_parent_and_descendants() {
+        let tree = DomainTree::from_entries(vec![
+            ("app.test".to_string(), 0),
+            ("api.app.test".to_string(), 1),
+            ("auth.app.test".to_string(), 2),
+            ("other.test".to_string(), 3),
+        ]);
+
+        let mut matched = tree.subtree("app.test");
+        matched.sort_unstable();
+        assert_eq!(matched, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn wildcard_entry_shares_its_parent_domain_node() {
+        let tree = DomainTree::from_entries(vec![("*.app.test".to_string(), 0)]);
+        assert_eq!(tree.get("app.test"), Some(0));
+        assert_eq!(tree.subtree("app.test"), vec![0]);
+    }
+
+    #[test]
+    fn contains_is_false_for_unknown_domains() {
+        let tree = DomainTree::from_entries(vec![("app.test".to_string(), 0)]);
+        assert!(!tree.contains("other.test"));
+    }
+}
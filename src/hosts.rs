@@ -0,0 +1,259 @@
+//! Tokenizes a hosts file into a `Vec<HostsPart>` once, so `add`, `dev`, and
+//! `prod` can all operate on the same in-memory model instead of each
+//! hand-rolling their own read/regex/write loop. Lines we don't touch are
+//! kept verbatim, so writing the model back out reproduces them exactly.
+//! Every line, including the last, is written back ending in a single `\n`:
+//! `\r\n` line endings and a missing final newline are not preserved.
+
+use std::fmt;
+use std::net::IpAddr;
+
+pub const MUKO_TAG: &str = "muko:";
+
+/// One logical piece of a hosts file, in on-disk order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostsPart {
+    /// An active `ip hostname...` line.
+    Entry(HostsEntry),
+    /// The same shape as `Entry`, but commented out with a leading `#`.
+    CommentedEntry(HostsEntry),
+    /// A line that isn't a recognizable entry: a header comment, a line
+    /// whose first token isn't a valid IP, etc. Kept verbatim.
+    Comment(String),
+    /// An empty (or whitespace-only) line. Kept verbatim.
+    Blank(String),
+}
+
+/// A parsed `ip hostname [hostname...] [# comment]` line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostsEntry {
+    /// Original indentation before the IP (or the `#` for commented entries).
+    pub leading_ws: String,
+    pub ip: IpAddr,
+    pub hostnames: Vec<String>,
+    /// Text following the `#`, if any, with the `#` itself stripped.
+    pub comment: Option<String>,
+    /// The original line's text after `leading_ws` and the commented-out
+    /// `#` (if any), kept verbatim so toggling preserves the exact
+    /// separators/alignment/tab-before-comment instead of reformatting them
+    /// with single spaces. `None` for freshly built entries, which fall
+    /// back to re-rendering from `ip`/`hostnames`/`comment`.
+    raw: Option<String>,
+}
+
+impl HostsEntry {
+    /// Whether this entry's trailing comment marks it as muko-managed.
+    pub fn is_muko(&self) -> bool {
+        self.comment
+            .as_deref()
+            .is_some_and(|c| c.trim_start().starts_with(MUKO_TAG))
+    }
+
+    /// The alias muko recorded in the trailing comment, if any.
+    pub fn muko_alias(&self) -> Option<&str> {
+        let rest = self.comment.as_deref()?.trim_start().strip_prefix(MUKO_TAG)?;
+        let alias = rest.trim();
+        if alias.is_empty() {
+            None
+        } else {
+            Some(alias)
+        }
+    }
+
+    /// True if `identifier` names this entry by domain or muko alias.
+    pub fn matches(&self, identifier: &str) -> bool {
+        self.hostnames.iter().any(|h| h == identifier) || self.muko_alias() == Some(identifier)
+    }
+
+    fn render(&self, commented: bool) -> String {
+        let prefix = if commented { "#" } else { "" };
+        if let Some(raw) = &self.raw {
+            return format!("{}{}{}", self.leading_ws, prefix, raw);
+        }
+        let mut line = format!(
+            "{}{}{} {}",
+            self.leading_ws,
+            prefix,
+            self.ip,
+            self.hostnames.join(" ")
+        );
+        if let Some(comment) = &self.comment {
+            line.push_str(" #");
+            line.push_str(comment);
+        }
+        line
+    }
+}
+
+impl HostsPart {
+    /// Builds a fresh muko-managed `Entry` line for `ip hostname #muko: alias`.
+    pub fn muko_entry(ip: IpAddr, hostname: &str, alias: &str) -> HostsPart {
+        let comment = if alias.is_empty() || alias == hostname {
+            MUKO_TAG.to_string()
+        } else {
+            format!("{} {}", MUKO_TAG, alias)
+        };
+        HostsPart::Entry(HostsEntry {
+            leading_ws: String::new(),
+            ip,
+            hostnames: vec![hostname.to_string()],
+            comment: Some(comment),
+            raw: None,
+        })
+    }
+
+    pub fn as_entry(&self) -> Option<&HostsEntry> {
+        match self {
+            HostsPart::Entry(e) | HostsPart::CommentedEntry(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// True if this is a muko-managed `Entry` or `CommentedEntry` (active or not).
+    pub fn is_muko(&self) -> bool {
+        self.as_entry().is_some_and(HostsEntry::is_muko)
+    }
+
+    /// Whether the entry is currently active (not commented out).
+    pub fn is_active(&self) -> bool {
+        matches!(self, HostsPart::Entry(_))
+    }
+
+    /// Flips `Entry` <-> `CommentedEntry`, preserving the entry's fields.
+    pub fn toggled(self) -> HostsPart {
+        match self {
+            HostsPart::Entry(e) => HostsPart::CommentedEntry(e),
+            HostsPart::CommentedEntry(e) => HostsPart::Entry(e),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for HostsPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostsPart::Entry(e) => write!(f, "{}", e.render(false)),
+            HostsPart::CommentedEntry(e) => write!(f, "{}", e.render(true)),
+            HostsPart::Comment(raw) => write!(f, "{}", raw),
+            HostsPart::Blank(raw) => write!(f, "{}", raw),
+        }
+    }
+}
+
+/// Tokenizes the full contents of a hosts file into ordered parts.
+pub fn parse(contents: &str) -> Vec<HostsPart> {
+    contents.lines().map(parse_line).collect()
+}
+
+/// Serializes parts back into hosts-file text, one line per part.
+pub fn serialize(parts: &[HostsPart]) -> String {
+    let mut out = String::new();
+    for part in parts {
+        out.push_str(&part.to_string());
+        out.push('\n');
+    }
+    out
+}
+
+fn parse_line(line: &str) -> HostsPart {
+    if line.trim().is_empty() {
+        return HostsPart::Blank(line.to_string());
+    }
+
+    let leading_ws_len = line.len() - line.trim_start().len();
+    let leading_ws = &line[..leading_ws_len];
+    let rest = &line[leading_ws_len..];
+
+    let (commented, body) = match rest.strip_prefix('#') {
+        Some(stripped) => (true, stripped),
+        None => (false, rest),
+    };
+
+    match parse_entry_body(body) {
+        Some((ip, hostnames, comment)) => {
+            let entry = HostsEntry {
+                leading_ws: leading_ws.to_string(),
+                ip,
+                hostnames,
+                comment,
+                raw: Some(body.to_string()),
+            };
+            if commented {
+                HostsPart::CommentedEntry(entry)
+            } else {
+                HostsPart::Entry(entry)
+            }
+        }
+        None => HostsPart::Comment(line.to_string()),
+    }
+}
+
+/// Parses `ip hostname [hostname...] [#comment]`, treating any mid-line `#`
+/// as the start of a trailing comment and either spaces or tabs as separators.
+fn parse_entry_body(body: &str) -> Option<(IpAddr, Vec<String>, Option<String>)> {
+    let (main, comment) = match body.find('#') {
+        Some(idx) => (&body[..idx], Some(body[idx + 1..].to_string())),
+        None => (body, None),
+    };
+
+    let mut tokens = main
+        .split(|c: char| c == ' ' || c == '\t')
+        .filter(|t| !t.is_empty());
+
+    let ip: IpAddr = tokens.next()?.parse().ok()?;
+    let hostnames: Vec<String> = tokens.map(|s| s.to_string()).collect();
+    if hostnames.is_empty() {
+        return None;
+    }
+
+    Some((ip, hostnames, comment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_blank_and_comment_lines() {
+        let input = "\n# a header\n  \n";
+        let parts = parse(input);
+        assert_eq!(serialize(&parts), input);
+    }
+
+    #[test]
+    fn parses_active_and_commented_muko_entries() {
+        let input = "127.0.0.1 draftlab.app #muko: lab\n#10.0.0.1 api.test #muko:\n";
+        let parts = parse(input);
+        assert_eq!(serialize(&parts), input);
+
+        let entry = parts[0].as_entry().unwrap();
+        assert!(entry.is_muko());
+        assert_eq!(entry.muko_alias(), Some("lab"));
+        assert!(parts[0].is_active());
+        assert!(!parts[1].is_active());
+    }
+
+    #[test]
+    fn accepts_tabs_and_ipv6() {
+        let input = "::1\tlocalhost6 #muko: six\n";
+        let parts = parse(input);
+        let entry = parts[0].as_entry().unwrap();
+        assert_eq!(entry.ip, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(entry.hostnames, vec!["localhost6".to_string()]);
+    }
+
+    #[test]
+    fn rejects_malformed_ip_as_plain_comment() {
+        let input = "not-an-ip somehost\n";
+        let parts = parse(input);
+        assert_eq!(parts, vec![HostsPart::Comment("not-an-ip somehost".to_string())]);
+    }
+
+    #[test]
+    fn toggle_flips_variant_and_preserves_fields() {
+        let parts = parse("127.0.0.1 app.test #muko: app\n");
+        let toggled = parts[0].clone().toggled();
+        assert!(!toggled.is_active());
+        assert_eq!(toggled.clone().toggled(), parts[0]);
+    }
+}
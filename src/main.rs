@@ -1,13 +1,18 @@
+mod domain_tree;
+mod hosts;
+mod profile;
+mod resolver;
+
+use domain_tree::DomainTree;
+
 use clap::{Parser, Subcommand};
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
-use dns_lookup::lookup_host;
-use regex::Regex;
-use std::fs::{self, OpenOptions};
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
-use std::thread;
-use std::time::Duration;
+use hosts::HostsPart;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "muko")]
@@ -15,6 +20,15 @@ use std::time::Duration;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// DNS resolver to use for PROD IP lookups, as ip:port (defaults to the
+    /// system resolver)
+    #[arg(long, global = true)]
+    resolver: Option<String>,
+
+    /// Hosts file to operate on (defaults to $MUKO_HOSTS_FILE, then /etc/hosts)
+    #[arg(long, global = true)]
+    file: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -34,13 +48,51 @@ enum Commands {
     },
     /// Set a domain to DEV mode (uncomment to use custom IP)
     Dev {
-        /// Domain name or alias
+        /// Domain name, alias, or profile name
         identifier: String,
+
+        /// Also toggle every subdomain under `identifier` (e.g. `app.test`
+        /// matches `api.app.test`, `auth.app.test`, ...)
+        #[arg(long)]
+        recursive: bool,
     },
     /// Set a domain to PROD mode (comment out to use real IP)
     Prod {
-        /// Domain name or alias
+        /// Domain name, alias, or profile name
         identifier: String,
+
+        /// Also toggle every subdomain under `identifier` (e.g. `app.test`
+        /// matches `api.app.test`, `auth.app.test`, ...)
+        #[arg(long)]
+        recursive: bool,
+    },
+    /// Manage named groups of domains that can be toggled together
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// List all profiles
+    List,
+    /// Show the domains/aliases in a profile
+    Show {
+        /// Profile name
+        name: String,
+    },
+    /// Create or replace a profile with the given domains
+    Add {
+        /// Profile name
+        name: String,
+        /// Domain names or aliases belonging to this profile
+        domains: Vec<String>,
+    },
+    /// Delete a profile
+    Remove {
+        /// Profile name
+        name: String,
     },
 }
 
@@ -53,36 +105,52 @@ struct MukoManagedDomain {
     prod_ip: Option<String>, // Real IP from DNS resolution
 }
 
-const HOSTS_FILE: &str = "/etc/hosts";
-const MUKO_TAG: &str = "#muko:";
+const DEFAULT_HOSTS_FILE: &str = "/etc/hosts";
+
+/// Resolves the hosts file to operate on: `--file`, then `$MUKO_HOSTS_FILE`,
+/// then `/etc/hosts`.
+fn hosts_file_path(file_arg: Option<PathBuf>) -> PathBuf {
+    file_arg
+        .or_else(|| std::env::var_os("MUKO_HOSTS_FILE").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_HOSTS_FILE))
+}
 
-fn main() {
+#[tokio::main]
+async fn main() {
     let cli = Cli::parse();
+    let resolver_override = cli.resolver.as_deref();
+    let hosts_path = hosts_file_path(cli.file);
 
     match cli.command {
         Some(Commands::Add { domain_name, ip, alias }) => {
             // Use domain_name as alias if not provided
             let alias_value = alias.unwrap_or_else(|| domain_name.clone());
-            if let Err(e) = add_domain(&domain_name, &ip, &alias_value) {
+            if let Err(e) = add_domain(&hosts_path, &domain_name, &ip, &alias_value, resolver_override).await {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
-        Some(Commands::Dev { identifier }) => {
-            if let Err(e) = set_mode(&identifier, true) {
+        Some(Commands::Dev { identifier, recursive }) => {
+            if let Err(e) = set_mode(&hosts_path, &identifier, true, recursive, resolver_override).await {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
-        Some(Commands::Prod { identifier }) => {
-            if let Err(e) = set_mode(&identifier, false) {
+        Some(Commands::Prod { identifier, recursive }) => {
+            if let Err(e) = set_mode(&hosts_path, &identifier, false, recursive, resolver_override).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Profile { action }) => {
+            if let Err(e) = run_profile_command(action) {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
         None => {
             // No command provided, just print the table
-            match parse_muko_entries() {
+            match parse_muko_entries(&hosts_path, resolver_override).await {
                 Ok(entries) => {
                     println!("Muko-managed domains:");
                     print_muko_table(&entries);
@@ -96,252 +164,266 @@ fn main() {
     }
 }
 
-fn add_domain(domain: &str, ip: &str, alias: &str) -> io::Result<()> {
-    // Read the current hosts file
-    let path = Path::new(HOSTS_FILE);
-    let file = fs::File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let mut lines = Vec::new();
-    let mut domain_found = false;
-    let new_entry = format!("{} {} {} {}", ip, domain, MUKO_TAG, alias);
-
-    // Process existing lines
-    for line in reader.lines() {
-        let line = line?;
-
-        // Quick check: if line doesn't contain the domain, keep it
-        if !line.contains(domain) {
-            lines.push(line);
-            continue;
+fn run_profile_command(action: ProfileCommands) -> io::Result<()> {
+    match action {
+        ProfileCommands::List => {
+            let profiles = profile::Profiles::load()?;
+            for (name, domains) in profiles.iter() {
+                println!("{}: {}", name, domains.join(", "));
+            }
         }
-
-        // Line contains domain - need to parse it carefully
-        let trimmed = line.trim_start();
-
-        // Handle commented lines (could be "# 127.0.0.1 draftlab.app")
-        let content = if trimmed.starts_with('#') {
-            trimmed.trim_start_matches('#').trim_start()
-        } else {
-            trimmed
-        };
-
-        // Split by whitespace to get [IP, hostname1, hostname2, ...]
-        // Stop at # if there's an inline comment
-        let before_comment = content.split('#').next().unwrap_or("");
-        let tokens: Vec<&str> = before_comment.split_whitespace().collect();
-
-        // Check if this is a valid host entry: at least IP + hostname
-        if tokens.len() >= 2 {
-            // tokens[0] should be an IP, tokens[1..] are hostnames
-            // Check if any hostname exactly matches our domain
-            if tokens[1..].iter().any(|&h| h == domain) {
-                // Found duplicate - skip this line, we'll replace it
-                domain_found = true;
-                continue;
+        ProfileCommands::Show { name } => {
+            let profiles = profile::Profiles::load()?;
+            let domains = profiles
+                .get(&name)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No profile named '{}'", name)))?;
+            println!("{}: {}", name, domains.join(", "));
+        }
+        ProfileCommands::Add { name, domains } => {
+            let mut profiles = profile::Profiles::load()?;
+            profiles.set(&name, domains);
+            profiles.save()?;
+            println!("✓ Profile '{}' saved", name);
+        }
+        ProfileCommands::Remove { name } => {
+            let mut profiles = profile::Profiles::load()?;
+            if !profiles.remove(&name) {
+                return Err(io::Error::new(io::ErrorKind::NotFound, format!("No profile named '{}'", name)));
             }
+            profiles.save()?;
+            println!("✓ Profile '{}' removed", name);
         }
-
-        // Not a match, keep the line
-        lines.push(line);
     }
+    Ok(())
+}
 
-    // Add the new entry
-    lines.push(new_entry);
+fn load_hosts_file(path: &Path) -> io::Result<Vec<HostsPart>> {
+    let contents = fs::read_to_string(path)?;
+    Ok(hosts::parse(&contents))
+}
 
-    // Write back to the hosts file
-    let mut file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(path)?;
+fn write_hosts_file(path: &Path, parts: &[HostsPart]) -> io::Result<()> {
+    fs::write(path, hosts::serialize(parts))
+}
+
+/// Every `(hostname, part index)` pair for `parts`, feeding the domain tree
+/// used by the duplicate check in `add_domain` and the `--recursive` walk
+/// in `set_mode`.
+fn entry_hostnames(parts: &[HostsPart]) -> Vec<(String, usize)> {
+    parts
+        .iter()
+        .enumerate()
+        .flat_map(|(i, part)| {
+            part.as_entry()
+                .into_iter()
+                .flat_map(move |e| e.hostnames.iter().map(move |h| (h.clone(), i)))
+        })
+        .collect()
+}
 
-    for line in &lines {
-        writeln!(file, "{}", line)?;
+/// Every muko-managed `(domain, part index)` pair, used for the
+/// `--recursive` subtree walk in `set_mode`.
+fn muko_domains(parts: &[HostsPart]) -> Vec<(String, usize)> {
+    parts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, part)| part.as_entry().filter(|e| e.is_muko()).map(|e| (e.hostnames[0].clone(), i)))
+        .collect()
+}
+
+async fn add_domain(
+    path: &Path,
+    domain: &str,
+    ip: &str,
+    alias: &str,
+    resolver_override: Option<&str>,
+) -> io::Result<()> {
+    let parsed_ip: IpAddr = ip.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid IP address", ip),
+        )
+    })?;
+
+    let mut parts = load_hosts_file(path)?;
+
+    // O(labels) duplicate check via the domain tree, instead of a linear
+    // `contains` scan over every hosts-file line.
+    let tree = DomainTree::from_entries(entry_hostnames(&parts));
+    let domain_found = tree.contains(domain);
+    if let Some(index) = tree.get(domain) {
+        parts.remove(index);
     }
 
+    let new_entry = HostsPart::muko_entry(parsed_ip, domain, alias);
+    let rendered = new_entry.to_string();
+    parts.push(new_entry);
+
+    write_hosts_file(path, &parts)?;
+
     // Notify the user
     if domain_found {
         println!("✓ Domain '{}' already existed and has been overwritten", domain);
     } else {
-        println!("✓ Domain '{}' has been added to {}", domain, HOSTS_FILE);
+        println!("✓ Domain '{}' has been added to {}", domain, path.display());
     }
-    println!("  {} {} {} {}", ip, domain, MUKO_TAG, alias);
+    println!("  {}", rendered.trim_start());
 
     // Print the updated muko-managed domains table
     println!("\nMuko-managed domains:");
-    let entries = parse_muko_entries()?;
+    let entries = parse_muko_entries(path, resolver_override).await?;
     print_muko_table(&entries);
 
     Ok(())
 }
 
-/// Set a muko-managed domain to DEV or PROD mode
-/// dev_mode: true for DEV (uncomment), false for PROD (comment out)
-fn set_mode(identifier: &str, dev_mode: bool) -> io::Result<()> {
-    let path = Path::new(HOSTS_FILE);
-    let file = fs::File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let mut lines = Vec::new();
-    let mut found = false;
-    let re = Regex::new(
-        r"^(#)?\s*((?:\d+\.\d+\.\d+\.\d+)|(?:[0-9a-fA-F:]+))\s+(\S+)\s+#muko:\s*(\S*)"
-    ).unwrap();
-
-    for line in reader.lines() {
-        let line = line?;
-
-        // Check if this is a muko-managed line
-        if line.contains(MUKO_TAG) {
-            if let Some(caps) = re.captures(&line) {
-                let domain = caps.get(3).map(|m| m.as_str()).unwrap();
-                let alias_str = caps.get(4).map(|m| m.as_str()).unwrap();
-
-                // Check if this line matches the identifier (domain or alias)
-                if domain == identifier || alias_str == identifier {
-                    found = true;
-                    let is_commented = caps.get(1).is_some();
-
-                    if dev_mode {
-                        // DEV mode: uncomment if necessary
-                        if is_commented {
-                            // Remove the leading #
-                            let uncommented = line.trim_start_matches('#').trim_start().to_string();
-                            lines.push(uncommented);
-                        } else {
-                            // Already uncommented
-                            lines.push(line);
-                        }
-                    } else {
-                        // PROD mode: comment out if necessary
-                        if !is_commented {
-                            // Add # at the beginning
-                            lines.push(format!("#{}", line));
-                        } else {
-                            // Already commented
-                            lines.push(line);
-                        }
-                    }
-                    continue;
-                }
-            }
+/// Set a muko-managed domain (or every domain in a profile, or every
+/// subdomain under a parent domain) to DEV or PROD mode. dev_mode: true for
+/// DEV (uncomment), false for PROD (comment out).
+///
+/// Every identifier is resolved to a list of matched entry indices before
+/// anything is written: a missing identifier (or an empty `--recursive`
+/// subtree) aborts before touching the file, so a failure on one entry
+/// never leaves it half-modified.
+async fn set_mode(
+    path: &Path,
+    identifier: &str,
+    dev_mode: bool,
+    recursive: bool,
+    resolver_override: Option<&str>,
+) -> io::Result<()> {
+    let mut parts = load_hosts_file(path)?;
+
+    let indices = if recursive {
+        let tree = DomainTree::from_entries(muko_domains(&parts));
+        let matched = tree.subtree(identifier);
+        if matched.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No muko-managed entry found under '{}'", identifier),
+            ));
         }
+        matched
+    } else {
+        let profiles = profile::Profiles::load()?;
+        let identifiers: Vec<String> = match profiles.get(identifier) {
+            Some(members) => members.to_vec(),
+            None => vec![identifier.to_string()],
+        };
 
-        // Not a match, keep the line as is
-        lines.push(line);
-    }
-
-    if !found {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("No muko-managed entry found for '{}'", identifier),
-        ));
+        let mut found = Vec::with_capacity(identifiers.len());
+        for id in &identifiers {
+            let index = parts
+                .iter()
+                .position(|part| part.is_muko() && part.as_entry().is_some_and(|e| e.matches(id)));
+            let Some(index) = index else {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No muko-managed entry found for '{}'", id),
+                ));
+            };
+            found.push(index);
+        }
+        found
+    };
+
+    let matched: Vec<String> = indices
+        .iter()
+        .map(|&i| parts[i].as_entry().unwrap().hostnames[0].clone())
+        .collect();
+
+    let mut changed = Vec::new();
+    for &index in &indices {
+        if parts[index].is_active() != dev_mode {
+            changed.push(parts[index].as_entry().unwrap().hostnames[0].clone());
+            let part = parts.remove(index);
+            parts.insert(index, part.toggled());
+        }
     }
 
-    // Write back to the hosts file
-    let mut file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(path)?;
-
-    for line in &lines {
-        writeln!(file, "{}", line)?;
-    }
+    write_hosts_file(path, &parts)?;
 
     // Notify the user
     let mode_name = if dev_mode { "DEV" } else { "PROD" };
     println!("✓ Set '{}' to {} mode", identifier, mode_name);
+    if indices.len() > 1 {
+        println!("  matched: {}", matched.join(", "));
+        if changed.is_empty() {
+            println!("  no entries changed (already {})", mode_name);
+        } else {
+            println!("  changed: {}", changed.join(", "));
+        }
+    }
 
     // Print the updated muko-managed domains table
     println!("\nMuko-managed domains:");
-    let entries = parse_muko_entries()?;
+    let entries = parse_muko_entries(path, resolver_override).await?;
     print_muko_table(&entries);
 
     Ok(())
 }
 
-/// Parse muko-managed entries from the hosts file
-fn parse_muko_entries() -> io::Result<Vec<MukoManagedDomain>> {
-    let path = Path::new(HOSTS_FILE);
-    let file = fs::File::open(path)?;
-    let reader = BufReader::new(file);
+/// Parse muko-managed entries from the hosts file, resolving PROD IPs for
+/// commented-out (PROD mode) entries concurrently.
+async fn parse_muko_entries(path: &Path, resolver_override: Option<&str>) -> io::Result<Vec<MukoManagedDomain>> {
+    let parts = load_hosts_file(path)?;
 
     let mut entries = Vec::new();
-
-    // Regex to parse muko-managed lines
-    // Captures: (optional #) (IP - IPv4 or IPv6) (domain) #muko: (alias)
-    // IPv4: \d+\.\d+\.\d+\.\d+
-    // IPv6: [0-9a-fA-F:]+
-    let re = Regex::new(
-        r"^(#)?\s*((?:\d+\.\d+\.\d+\.\d+)|(?:[0-9a-fA-F:]+))\s+(\S+)\s+#muko:\s*(\S*)"
-    ).unwrap();
-
-    for line in reader.lines() {
-        let line = line?;
-
-        // Quick check: if line doesn't contain muko tag, skip it
-        if !line.contains(MUKO_TAG) {
+    for part in &parts {
+        let active = part.is_active();
+        let Some(entry) = part.as_entry() else {
+            continue;
+        };
+        if !entry.is_muko() {
             continue;
         }
 
-        // Try to parse with regex
-        if let Some(caps) = re.captures(&line) {
-            let active = caps.get(1).is_none(); // If no # at start, it's active
-            let ip = caps.get(2).map(|m| m.as_str().to_string()).unwrap();
-            let domain = caps.get(3).map(|m| m.as_str().to_string()).unwrap();
-            let alias_str = caps.get(4).map(|m| m.as_str().to_string()).unwrap();
-
-            let alias = if alias_str.is_empty() {
-                None
-            } else {
-                Some(alias_str)
-            };
-
-            // Resolve DNS to get the real IP address
-            // Only do this in PROD mode; in DEV mode we don't need it
-            let prod_ip = if !active {
-                // PROD mode: retry up to 3 times until we get a different IP than dev_ip
-                let mut resolved_ip = None;
-                for attempt in 1..=3 {
-                    if let Some(lookup_ip) = lookup_host(&domain)
-                        .ok()
-                        .and_then(|ips| ips.into_iter().next())
-                        .map(|ip| ip.to_string())
-                    {
-                        // If the resolved IP is different from dev IP, we found the real prod IP
-                        if lookup_ip != ip {
-                            resolved_ip = Some(lookup_ip);
-                            break;
-                        }
-                        resolved_ip = Some(lookup_ip);
-                    }
-
-                    // Wait a bit before retrying (except on last attempt)
-                    if attempt < 3 {
-                        thread::sleep(Duration::from_millis(100));
-                    }
-                }
-                resolved_ip
-            } else {
-                // DEV mode: no lookup needed, won't be displayed
-                None
-            };
+        entries.push(MukoManagedDomain {
+            ip: entry.ip.to_string(),
+            domain: entry.hostnames[0].clone(),
+            alias: entry.muko_alias().map(|a| a.to_string()),
+            active,
+            prod_ip: None,
+        });
+    }
 
-            entries.push(MukoManagedDomain {
-                ip,
-                domain,
-                alias,
-                active,
-                prod_ip,
-            });
+    // Only PROD-mode (inactive) entries need a DNS lookup.
+    let lookups: Vec<(String, String)> = entries
+        .iter()
+        .filter(|e| !e.active)
+        .map(|e| (e.domain.clone(), e.ip.clone()))
+        .collect();
+
+    if !lookups.is_empty() {
+        let resolver = resolver::build_resolver(resolver_override)?;
+        let prod_ips = resolver::resolve_prod_ips(&resolver, &lookups).await;
+        let mut prod_ips = prod_ips.into_iter();
+        for entry in entries.iter_mut().filter(|e| !e.active) {
+            entry.prod_ip = prod_ips.next().flatten();
         }
     }
 
     Ok(entries)
 }
 
-/// Print muko-managed domains as a formatted table
+/// The reversed-label sort key for `domain` (`api.app.test` ->
+/// `["test", "app", "api"]`), so that grouping by it clusters a domain with
+/// its subdomains.
+fn reversed_domain_key(domain: &str) -> Vec<String> {
+    domain
+        .strip_prefix("*.")
+        .unwrap_or(domain)
+        .split('.')
+        .rev()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Print muko-managed domains as a formatted table, grouped by parent domain
 fn print_muko_table(entries: &[MukoManagedDomain]) {
+    let mut sorted: Vec<&MukoManagedDomain> = entries.iter().collect();
+    sorted.sort_by_key(|e| reversed_domain_key(&e.domain));
+
     let mut table = Table::new();
     table
         .load_preset(UTF8_FULL)
@@ -354,7 +436,7 @@ fn print_muko_table(entries: &[MukoManagedDomain]) {
             Cell::new("Prod IP").add_attribute(Attribute::Bold),
         ]);
 
-    for entry in entries {
+    for entry in sorted {
         let mode = if entry.active {
             // Not commented out = using custom IP = DEV mode
             Cell::new("DEV").fg(Color::Green)
@@ -0,0 +1,68 @@
+//! Named groups of domains that can be flipped between DEV and PROD
+//! together, e.g. `muko dev frontend` instead of toggling each host one
+//! by one. Profiles are stored as `name = [domain, ...]` in
+//! `muko/profiles.toml` under the platform's config directory
+//! (`~/.config/muko/profiles.toml` on Linux).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Profiles {
+    #[serde(flatten)]
+    profiles: BTreeMap<String, Vec<String>>,
+}
+
+impl Profiles {
+    /// Loads profiles from disk, returning an empty set if the config file
+    /// doesn't exist yet.
+    pub fn load() -> io::Result<Profiles> {
+        let path = config_path()?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Profiles::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = config_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, contents)
+    }
+
+    /// The domains/aliases belonging to `name`, if it exists.
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.profiles.get(name).map(Vec::as_slice)
+    }
+
+    pub fn set(&mut self, name: &str, domains: Vec<String>) {
+        self.profiles.insert(name.to_string(), domains);
+    }
+
+    /// Removes a profile, returning whether it existed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.profiles.remove(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
+        self.profiles.iter()
+    }
+}
+
+fn config_path() -> io::Result<PathBuf> {
+    let base = dirs::config_dir().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "could not determine the config directory")
+    })?;
+    Ok(base.join("muko").join("profiles.toml"))
+}
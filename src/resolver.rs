@@ -0,0 +1,88 @@
+//! Concurrent, timeout-bounded DNS resolution for PROD-mode muko entries.
+//!
+//! `parse_muko_entries` used to call the blocking `dns_lookup` crate in a
+//! per-entry loop, so a table of N PROD domains resolved serially and one
+//! slow name could stall the whole command. This module resolves every
+//! entry concurrently with `hickory-resolver`, bounded by a per-query
+//! timeout and a global deadline for the whole batch.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures::future::join_all;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+
+/// Per-query timeout: a single slow name should never stall the batch.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// Overall deadline for resolving every PROD entry in one command invocation.
+const GLOBAL_DEADLINE: Duration = Duration::from_secs(5);
+
+/// Builds a resolver that either talks to `resolver_override` (`ip:port`)
+/// or falls back to the system resolver config (`/etc/resolv.conf`).
+pub fn build_resolver(resolver_override: Option<&str>) -> io::Result<TokioAsyncResolver> {
+    let mut opts = ResolverOpts::default();
+    opts.timeout = QUERY_TIMEOUT;
+
+    let config = match resolver_override {
+        Some(addr) => {
+            let socket: SocketAddr = addr.parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("'{}' is not a valid resolver address, expected ip:port", addr),
+                )
+            })?;
+            ResolverConfig::from_parts(
+                None,
+                vec![],
+                NameServerConfigGroup::from_ips_clear(&[socket.ip()], socket.port(), true),
+            )
+        }
+        None => {
+            hickory_resolver::system_conf::read_system_conf()
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                .0
+        }
+    };
+
+    Ok(TokioAsyncResolver::tokio(config, opts))
+}
+
+/// Resolves `domain`'s PROD IP: the first A/AAAA answer that differs from
+/// `dev_ip`, falling back to the first answer if every answer matches it.
+/// Returns `None` on a timeout or resolution failure.
+async fn resolve_one(resolver: &TokioAsyncResolver, domain: &str, dev_ip: &str) -> Option<String> {
+    let lookup = tokio::time::timeout(QUERY_TIMEOUT, resolver.lookup_ip(domain))
+        .await
+        .ok()?
+        .ok()?;
+
+    let mut answers = lookup.iter();
+    let first = answers.next()?;
+    let differing = std::iter::once(first)
+        .chain(answers)
+        .find(|ip| ip.to_string() != dev_ip);
+
+    Some(differing.unwrap_or(first).to_string())
+}
+
+/// Resolves PROD IPs for every `(domain, dev_ip)` pair concurrently, bounded
+/// by a single global deadline across the whole batch. Each lookup races
+/// independently against the same deadline, so entries that finish before
+/// it keep their resolved IP; only entries still outstanding when the
+/// deadline passes resolve to `None`.
+pub async fn resolve_prod_ips(
+    resolver: &TokioAsyncResolver,
+    entries: &[(String, String)],
+) -> Vec<Option<String>> {
+    let deadline = tokio::time::Instant::now() + GLOBAL_DEADLINE;
+
+    join_all(entries.iter().map(|(domain, dev_ip)| async move {
+        tokio::time::timeout_at(deadline, resolve_one(resolver, domain, dev_ip))
+            .await
+            .ok()
+            .flatten()
+    }))
+    .await
+}
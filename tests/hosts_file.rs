@@ -0,0 +1,146 @@
+//! Integration tests that drive the built `muko` binary against fixture
+//! hosts files in a tempdir, asserting exact byte output after add/dev/prod
+//! round-trips. Using `--file` means none of this touches the real system
+//! hosts file and needs no root.
+
+use std::io::Write;
+use std::process::Command;
+
+fn muko() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_muko"))
+}
+
+fn write_fixture(dir: &tempfile::TempDir, name: &str, contents: &str) -> std::path::PathBuf {
+    let path = dir.path().join(name);
+    let mut file = std::fs::File::create(&path).unwrap();
+    file.write_all(contents.as_bytes()).unwrap();
+    path
+}
+
+#[test]
+fn add_appends_a_new_muko_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(&dir, "hosts", "127.0.0.1 localhost\n");
+
+    let status = muko()
+        .args(["--file", path.to_str().unwrap(), "add", "app.test", "--ip", "127.0.0.2"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, "127.0.0.1 localhost\n127.0.0.2 app.test #muko:\n");
+}
+
+#[test]
+fn add_replaces_a_duplicate_domain_and_moves_it_to_the_end() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(
+        &dir,
+        "hosts",
+        "127.0.0.1 localhost\n127.0.0.2 app.test #muko: old\n10.0.0.1 other.test\n",
+    );
+
+    let status = muko()
+        .args(["--file", path.to_str().unwrap(), "add", "app.test", "--ip", "127.0.0.3", "--alias", "new"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        contents,
+        "127.0.0.1 localhost\n10.0.0.1 other.test\n127.0.0.3 app.test #muko: new\n"
+    );
+}
+
+#[test]
+fn prod_comments_out_a_dev_entry_preserving_untouched_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(
+        &dir,
+        "hosts",
+        "# A hand-written header\n127.0.0.1 localhost\n\tfe80::1 dev6.test\t# keep this comment\n127.0.0.2 app.test #muko: app\n",
+    );
+
+    let status = muko()
+        .args(["--file", path.to_str().unwrap(), "prod", "app"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        contents,
+        "# A hand-written header\n127.0.0.1 localhost\n\tfe80::1 dev6.test\t# keep this comment\n#127.0.0.2 app.test #muko: app\n"
+    );
+}
+
+#[test]
+fn dev_uncomments_a_prod_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(&dir, "hosts", "#127.0.0.1 app.test #muko: app\n");
+
+    let status = muko()
+        .args(["--file", path.to_str().unwrap(), "dev", "app"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, "127.0.0.1 app.test #muko: app\n");
+}
+
+#[test]
+fn dev_on_unknown_identifier_fails_without_modifying_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let original = "127.0.0.1 app.test #muko: app\n";
+    let path = write_fixture(&dir, "hosts", original);
+
+    let status = muko()
+        .args(["--file", path.to_str().unwrap(), "dev", "does-not-exist"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, original);
+}
+
+#[test]
+fn muko_hosts_file_env_var_is_respected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(&dir, "hosts", "127.0.0.1 app.test #muko: app\n");
+
+    let status = muko()
+        .env("MUKO_HOSTS_FILE", &path)
+        .args(["prod", "app"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, "#127.0.0.1 app.test #muko: app\n");
+}
+
+#[test]
+fn prod_recursive_toggles_every_subdomain() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_fixture(
+        &dir,
+        "hosts",
+        "127.0.0.1 app.test #muko: app\n127.0.0.2 api.app.test #muko:\n127.0.0.3 other.test #muko:\n",
+    );
+
+    let status = muko()
+        .args(["--file", path.to_str().unwrap(), "prod", "app.test", "--recursive"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        contents,
+        "#127.0.0.1 app.test #muko: app\n#127.0.0.2 api.app.test #muko:\n127.0.0.3 other.test #muko:\n"
+    );
+}